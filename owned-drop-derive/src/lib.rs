@@ -0,0 +1,197 @@
+//! Derive macro companion to `owned_drop`, generating ordered, by-value
+//! field finalization so manual `ManuallyDrop` field ordering is no longer
+//! needed for the common case.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, LitInt, Member};
+
+/// Derives [`OwnedDroppable`](owned_drop::OwnedDroppable) for a struct.
+///
+/// Fields annotated `#[owned_drop(order = N)]` are moved out by value and
+/// have their own `drop_owned` invoked in ascending `N` order; unannotated
+/// fields are left untouched and finalized by their normal drop glue once
+/// all ordered fields have been handled. With the `std` feature, a panic
+/// while finalizing any field — ordered or unannotated — is caught and does
+/// not stop the remaining fields from running; at most one caught payload
+/// (the first one encountered) is then routed through
+/// [`owned_drop::handle_owned_drop_panic`] once every field has been
+/// finalized. Without `std` a panic propagates immediately (and, since
+/// `this` is a `ManuallyDrop`, leaks the fields not yet finalized), the same
+/// tradeoff `DropOwned`'s own `Drop` impl makes.
+///
+/// The `std` check in the generated code is `#[cfg(feature = "std")]`, which
+/// is evaluated in the crate that applies the derive, not in `owned_drop`:
+/// panics are caught only when that crate has its own `std` feature enabled,
+/// and that feature must also enable `owned_drop/std`, otherwise the call to
+/// `owned_drop::handle_owned_drop_panic` does not compile.
+///
+/// An ordered field's type must implement [`OwnedDroppable`](owned_drop::OwnedDroppable)
+/// itself; this is a hard requirement, not a best-effort fallback, so annotating
+/// a field whose type does not implement it is a compile error.
+///
+/// # Example
+/// ```ignore
+/// #[derive(OwnedDroppable)]
+/// struct FruitBox {
+///     #[owned_drop(order = 0)]
+///     crate_handle: CrateHandle,
+///     #[owned_drop(order = 1)]
+///     truck_handle: TruckHandle,
+///     label: String,
+/// }
+/// ```
+///
+/// Two fields sharing the same `order` are rejected at expansion time rather
+/// than silently falling back to declaration order:
+/// ```compile_fail
+/// use owned_drop::OwnedDroppable;
+///
+/// struct Leaf;
+/// impl OwnedDroppable for Leaf {
+///     fn drop_owned(self) {}
+/// }
+///
+/// #[derive(OwnedDroppable)]
+/// struct Dup {
+///     #[owned_drop(order = 0)]
+///     a: Leaf,
+///     #[owned_drop(order = 0)]
+///     b: Leaf,
+/// }
+/// ```
+#[proc_macro_derive(OwnedDroppable, attributes(owned_drop))]
+pub fn derive_owned_droppable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+
+    let fields = match input.data {
+        Data::Struct(data) => data.fields,
+        _ => {
+            return syn::Error::new(
+                Span::call_site(),
+                "OwnedDroppable can only be derived for structs",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+
+    let mut ordered: Vec<(u64, Member)> = Vec::new();
+    let mut rest: Vec<Member> = Vec::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let member = field
+            .ident
+            .clone()
+            .map(Member::Named)
+            .unwrap_or_else(|| Member::Unnamed(i.into()));
+
+        match parse_order(field) {
+            Some(Ok(order)) => ordered.push((order, member)),
+            Some(Err(err)) => return err.to_compile_error().into(),
+            None => rest.push(member),
+        }
+    }
+
+    let mut seen_orders = std::collections::HashSet::new();
+    for (order, member) in &ordered {
+        if !seen_orders.insert(*order) {
+            return syn::Error::new_spanned(
+                member,
+                format!("duplicate `#[owned_drop(order = {order})]`, each ordered field needs a distinct order"),
+            )
+            .to_compile_error()
+            .into();
+        }
+    }
+
+    ordered.sort_by_key(|(order, _)| *order);
+
+    let ordered_members = ordered.iter().map(|(_, member)| member);
+    let rest_members = rest.iter();
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let expanded = quote! {
+        impl #impl_generics ::owned_drop::OwnedDroppable for #name #ty_generics #where_clause {
+            fn drop_owned(self) {
+                let mut this = ::core::mem::ManuallyDrop::new(self);
+                // At most one caught panic is re-raised, and only once every field
+                // below has had a chance to finalize; std-gated since catch_unwind
+                // requires std (without it a panic propagates immediately instead,
+                // leaking the fields that have not yet been read out of `this`).
+                #[cfg(feature = "std")]
+                let mut __owned_drop_panic: ::std::option::Option<::owned_drop::OwnedDropPanicPayload> = ::std::option::Option::None;
+                #(
+                    // SAFETY: each ordered field is read out of `this` exactly once
+                    // (the annotation order is validated to be free of duplicates at
+                    // expansion time), and `this` never runs its normal drop glue
+                    // since it is wrapped in a `ManuallyDrop`.
+                    let field = unsafe { ::core::ptr::read(&this.#ordered_members) };
+                    #[cfg(feature = "std")]
+                    {
+                        let caught = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+                            ::owned_drop::OwnedDroppable::drop_owned(field)
+                        }));
+                        if let ::std::result::Result::Err(payload) = caught {
+                            if __owned_drop_panic.is_none() {
+                                __owned_drop_panic = ::std::option::Option::Some(payload);
+                            }
+                        }
+                    }
+                    #[cfg(not(feature = "std"))]
+                    ::owned_drop::OwnedDroppable::drop_owned(field);
+                )*
+                #(
+                    // SAFETY: unannotated fields are never read above, so this is the
+                    // only place they are dropped, via their normal drop glue.
+                    #[cfg(feature = "std")]
+                    {
+                        let caught = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| unsafe {
+                            ::core::ptr::drop_in_place(&mut this.#rest_members)
+                        }));
+                        if let ::std::result::Result::Err(payload) = caught {
+                            if __owned_drop_panic.is_none() {
+                                __owned_drop_panic = ::std::option::Option::Some(payload);
+                            }
+                        }
+                    }
+                    #[cfg(not(feature = "std"))]
+                    unsafe { ::core::ptr::drop_in_place(&mut this.#rest_members) };
+                )*
+                #[cfg(feature = "std")]
+                if let ::std::option::Option::Some(payload) = __owned_drop_panic {
+                    ::owned_drop::handle_owned_drop_panic(payload);
+                }
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+fn parse_order(field: &syn::Field) -> Option<syn::Result<u64>> {
+    for attr in &field.attrs {
+        if !attr.path().is_ident("owned_drop") {
+            continue;
+        }
+        let mut order = None;
+        if let Err(err) = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("order") {
+                let value: LitInt = meta.value()?.parse()?;
+                order = Some(value.base10_parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported owned_drop attribute, expected `order = N`"))
+            }
+        }) {
+            return Some(Err(err));
+        }
+        return Some(order.ok_or_else(|| {
+            syn::Error::new_spanned(attr, "expected `#[owned_drop(order = N)]`")
+        }));
+    }
+    None
+}
@@ -1,5 +1,37 @@
 use core::mem::ManuallyDrop;
 use core::ops::{Deref, DerefMut};
+#[cfg(feature = "derive")]
+pub use owned_drop_derive::OwnedDroppable;
+#[cfg(feature = "std")]
+use std::any::Any;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+/// The payload a panicking `drop_owned` call was caught with.
+#[cfg(feature = "std")]
+pub type OwnedDropPanicPayload = Box<dyn Any + Send>;
+
+#[cfg(feature = "std")]
+type OwnedDropPanicHook = Box<dyn Fn(OwnedDropPanicPayload) + Send + Sync>;
+
+#[cfg(feature = "std")]
+static OWNED_DROP_PANIC_HOOK: RwLock<Option<OwnedDropPanicHook>> = RwLock::new(None);
+
+/// Installs a hook that is invoked whenever `drop_owned` panics while the
+/// `DropOwned` destructor runs during an unwind already in progress, instead
+/// of letting the payload resume the unwind (which would abort the process).
+///
+/// When no panic is in progress, a panicking `drop_owned` keeps resuming the
+/// unwind as usual unless this hook is installed, in which case the hook is
+/// called instead.
+///
+/// Only available with the `std` feature, since `catch_unwind` requires `std`.
+#[cfg(feature = "std")]
+pub fn set_owned_drop_panic_hook(hook: impl Fn(OwnedDropPanicPayload) + Send + Sync + 'static) {
+    *OWNED_DROP_PANIC_HOOK.write().unwrap() = Some(Box::new(hook));
+}
 
 /// Creates a new instance of `DropOwned` containing
 /// the passed `val`.
@@ -10,6 +42,10 @@ pub const fn drop_owned<T: OwnedDroppable>(val: T) -> DropOwned<T> {
 
 /// This trait has to be implemented for types that
 /// can be dropped ownedly.
+///
+/// With the `derive` feature, `#[derive(OwnedDroppable)]` implements this for
+/// a struct by moving out fields annotated `#[owned_drop(order = N)]` and
+/// finalizing them in ascending `N` order.
 pub trait OwnedDroppable: Sized {
     /// This method is called once the `OwnedDrop`
     /// got dropped and provides the dropped instance to
@@ -17,6 +53,30 @@ pub trait OwnedDroppable: Sized {
     fn drop_owned(self);
 }
 
+/// This trait has to be implemented for types whose owned drop can fail,
+/// such as finalizers that flush or close an underlying resource.
+///
+/// Every `TryOwnedDroppable` is also an [`OwnedDroppable`], whose `drop_owned`
+/// runs `try_drop_owned` as a best-effort fallback, discarding the error. Use
+/// [`DropOwned::close`] instead to surface the error to the caller.
+pub trait TryOwnedDroppable: Sized {
+    /// The error produced when finalization fails.
+    type Error;
+
+    /// This method is called once the `DropOwned` got dropped or [`DropOwned::close`]d
+    /// and provides the dropped instance to the implementor, allowing it to report
+    /// failure.
+    fn try_drop_owned(self) -> Result<(), Self::Error>;
+}
+
+impl<T: TryOwnedDroppable> OwnedDroppable for T {
+    #[inline]
+    fn drop_owned(self) {
+        // best-effort: there is no caller left to hand the error to, so it is discarded
+        let _ = self.try_drop_owned();
+    }
+}
+
 /// Once this type gets dropped, the contained value
 /// is passed to the `drop_owned` function it has to implement.
 ///
@@ -41,15 +101,65 @@ pub trait OwnedDroppable: Sized {
 /// drop(x);
 /// assert_eq!(v, vec![Box::new(10)])
 /// ```
-
-pub struct DropOwned<T: OwnedDroppable>(ManuallyDrop<T>);
+pub struct DropOwned<T: OwnedDroppable> {
+    inner: ManuallyDrop<T>,
+    /// Whether `drop_owned` still runs when this wrapper is dropped. Cleared
+    /// by [`defuse`](Self::defuse) and set again by [`rearm`](Self::rearm).
+    armed: bool,
+}
 
 impl<T: OwnedDroppable> DropOwned<T> {
     /// Creates a new instance of `DropOwned` containing
     /// the passed `val`.
     #[inline]
     pub const fn new(val: T) -> Self {
-        Self(ManuallyDrop::new(val))
+        Self {
+            inner: ManuallyDrop::new(val),
+            armed: true,
+        }
+    }
+
+    /// Cancels the pending `drop_owned` call. While defused, dropping this
+    /// wrapper finalizes the inner value with normal drop glue instead, and
+    /// `Deref`/`DerefMut` keep working exactly as before.
+    ///
+    /// # Example
+    /// ```
+    /// use owned_drop::{DropOwned, OwnedDroppable};
+    ///
+    /// struct PushVec<'a, T> {
+    ///     elt: T,
+    ///     vec: &'a mut Vec<T>,
+    /// }
+    ///
+    /// impl<'a, T> OwnedDroppable for PushVec<'a, T> {
+    ///     fn drop_owned(self) {
+    ///         self.vec.push(self.elt)
+    ///     }
+    /// }
+    ///
+    /// let mut v = vec![];
+    /// let mut x = DropOwned::new(PushVec{elt: Box::new(5), vec: &mut v});
+    /// x.defuse();
+    /// drop(x);
+    /// assert_eq!(v, vec![]);
+    /// ```
+    #[inline]
+    pub fn defuse(&mut self) {
+        self.armed = false;
+    }
+
+    /// Re-enables a previously [`defuse`](Self::defuse)d wrapper, so `drop_owned`
+    /// runs again once this wrapper is dropped.
+    #[inline]
+    pub fn rearm(&mut self) {
+        self.armed = true;
+    }
+
+    /// Returns whether `drop_owned` will run when this wrapper is dropped.
+    #[inline]
+    pub fn is_armed(&self) -> bool {
+        self.armed
     }
 
     /// Consumes the `DropOwned` to produces its inner value
@@ -82,7 +192,39 @@ impl<T: OwnedDroppable> DropOwned<T> {
         let mut manual_drop = ManuallyDrop::new(slot);
         // SAFETY the inner `ManuallyDrop` will never get used again since put it in the outer
         // `ManuallyDrop` which will cause use to forget it
-        unsafe { ManuallyDrop::take(&mut manual_drop.0) }
+        unsafe { ManuallyDrop::take(&mut manual_drop.inner) }
+    }
+}
+
+impl<T: TryOwnedDroppable> DropOwned<T> {
+    /// Consumes the `DropOwned`, running its [`TryOwnedDroppable::try_drop_owned`]
+    /// and surfacing the result to the caller, unlike the implicit `Drop` impl
+    /// which can only discard a finalization failure.
+    ///
+    /// # Example
+    /// ```
+    /// use owned_drop::{DropOwned, TryOwnedDroppable};
+    ///
+    /// struct Closeable(bool);
+    ///
+    /// impl TryOwnedDroppable for Closeable {
+    ///     type Error = &'static str;
+    ///
+    ///     fn try_drop_owned(self) -> Result<(), Self::Error> {
+    ///         if self.0 {
+    ///             Ok(())
+    ///         } else {
+    ///             Err("failed to close")
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let x = DropOwned::new(Closeable(false));
+    /// assert_eq!(DropOwned::close(x), Err("failed to close"));
+    /// ```
+    #[inline]
+    pub fn close(slot: Self) -> Result<(), T::Error> {
+        DropOwned::into_inner(slot).try_drop_owned()
     }
 }
 
@@ -98,21 +240,68 @@ impl<T: OwnedDroppable> Deref for DropOwned<T> {
 
     #[inline]
     fn deref(&self) -> &Self::Target {
-        self.0.deref()
+        self.inner.deref()
     }
 }
 
 impl<T: OwnedDroppable> DerefMut for DropOwned<T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.0.deref_mut()
+        self.inner.deref_mut()
     }
 }
 
 impl<T: OwnedDroppable> Drop for DropOwned<T> {
     #[inline]
     fn drop(&mut self) {
-        // SAFETY this `ManuallyDrop` will never get used again since we are inside the destructor
-        unsafe { ManuallyDrop::take(&mut self.0) }.drop_owned();
+        if !self.armed {
+            // SAFETY this `ManuallyDrop` will never get used again since we are inside the
+            // destructor; defused wrappers fall back to the inner value's normal drop glue.
+            unsafe { ManuallyDrop::drop(&mut self.inner) };
+            return;
+        }
+        // SAFETY this `ManuallyDrop` will never get used again since we are inside the destructor,
+        // and the take happens exactly once, before any catch_unwind boundary below, so the value
+        // is never taken twice regardless of which path handles the resulting panic.
+        let val = unsafe { ManuallyDrop::take(&mut self.inner) };
+        finalize_owned_drop(val);
+    }
+}
+
+/// Calls `val.drop_owned()`, applying the same panic-safety policy as
+/// `DropOwned`'s `Drop` impl: with the `std` feature, a panic is caught and
+/// routed through [`handle_owned_drop_panic`]; without it, `drop_owned` runs
+/// directly, since `catch_unwind` requires `std`.
+///
+/// This is `pub` but hidden from the docs. The `derive` macro's generated
+/// `drop_owned` does not call it: that code runs its own `catch_unwind` per
+/// field so it can keep the first payload and route it through
+/// [`handle_owned_drop_panic`] only after every field has been finalized.
+#[doc(hidden)]
+#[inline]
+pub fn finalize_owned_drop<T: OwnedDroppable>(val: T) {
+    #[cfg(feature = "std")]
+    if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| val.drop_owned())) {
+        handle_owned_drop_panic(payload);
+    }
+    #[cfg(not(feature = "std"))]
+    val.drop_owned();
+}
+
+/// Routes a `drop_owned` panic payload caught during destruction to the
+/// installed hook, falling back to resuming the unwind when no hook is
+/// installed and doing so wouldn't itself trigger a double-panic abort.
+///
+/// This is `pub` (but hidden) so the `derive` macro's generated `drop_owned`
+/// can apply the same policy to the single payload it re-raises after all
+/// fields have been finalized.
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub fn handle_owned_drop_panic(payload: OwnedDropPanicPayload) {
+    let hook = OWNED_DROP_PANIC_HOOK.read().unwrap();
+    match hook.as_ref() {
+        Some(hook) => hook(payload),
+        None if !std::thread::panicking() => std::panic::resume_unwind(payload),
+        None => drop(payload),
     }
 }
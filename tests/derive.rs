@@ -0,0 +1,155 @@
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use owned_drop::{set_owned_drop_panic_hook, DropOwned, OwnedDroppable};
+use std::cell::{Cell, RefCell};
+use std::panic::AssertUnwindSafe;
+
+thread_local! {
+    static LOG: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    static HOOK_ENABLED: Cell<bool> = const { Cell::new(false) };
+    static HOOK_RAN: Cell<u32> = const { Cell::new(0) };
+}
+
+fn take_log() -> Vec<&'static str> {
+    LOG.with(|log| std::mem::take(&mut *log.borrow_mut()))
+}
+
+// The panic hook is process-global while tests run on parallel threads, so it
+// only absorbs panics on threads that called this and re-raises them everywhere
+// else, just as happens when no hook is installed.
+fn install_counting_hook() {
+    set_owned_drop_panic_hook(|payload| {
+        if !HOOK_ENABLED.with(Cell::get) {
+            std::panic::resume_unwind(payload);
+        }
+        HOOK_RAN.with(|ran| ran.set(ran.get() + 1));
+        LOG.with(|log| log.borrow_mut().push("hook"));
+    });
+    HOOK_ENABLED.with(|enabled| enabled.set(true));
+    HOOK_RAN.with(|ran| ran.set(0));
+}
+
+struct Logged(&'static str);
+
+impl OwnedDroppable for Logged {
+    fn drop_owned(self) {
+        LOG.with(|log| log.borrow_mut().push(self.0));
+    }
+}
+
+struct Panicking(&'static str);
+
+impl OwnedDroppable for Panicking {
+    fn drop_owned(self) {
+        LOG.with(|log| log.borrow_mut().push(self.0));
+        panic!("boom from {}", self.0);
+    }
+}
+
+struct Plain(&'static str);
+
+impl Drop for Plain {
+    fn drop(&mut self) {
+        LOG.with(|log| log.borrow_mut().push(self.0));
+    }
+}
+
+struct PanickingPlain(&'static str);
+
+impl Drop for PanickingPlain {
+    fn drop(&mut self) {
+        LOG.with(|log| log.borrow_mut().push(self.0));
+        panic!("boom from {}", self.0);
+    }
+}
+
+#[derive(OwnedDroppable)]
+struct Ordered {
+    #[owned_drop(order = 1)]
+    second: Logged,
+    #[owned_drop(order = 0)]
+    first: Logged,
+    rest: Plain,
+}
+
+#[test]
+fn finalizes_ordered_fields_ascending_then_drops_rest() {
+    let value = Ordered {
+        second: Logged("second"),
+        first: Logged("first"),
+        rest: Plain("rest"),
+    };
+
+    drop(DropOwned::new(value));
+
+    assert_eq!(take_log(), vec!["first", "second", "rest"]);
+}
+
+#[derive(OwnedDroppable)]
+struct PanicsFirst {
+    #[owned_drop(order = 0)]
+    a: Panicking,
+    #[owned_drop(order = 1)]
+    b: Logged,
+    rest: Plain,
+}
+
+#[test]
+fn panic_in_one_field_does_not_skip_the_rest() {
+    let value = PanicsFirst {
+        a: Panicking("a"),
+        b: Logged("b"),
+        rest: Plain("rest"),
+    };
+
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        drop(DropOwned::new(value));
+    }));
+
+    assert!(result.is_err(), "the panic should still be re-raised");
+    assert_eq!(take_log(), vec!["a", "b", "rest"]);
+}
+
+#[test]
+fn panic_in_one_field_is_routed_through_the_hook_exactly_once() {
+    install_counting_hook();
+
+    let value = PanicsFirst {
+        a: Panicking("a"),
+        b: Logged("b"),
+        rest: Plain("rest"),
+    };
+
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        drop(DropOwned::new(value));
+    }));
+
+    assert!(result.is_ok(), "the hook should have absorbed the panic");
+    assert_eq!(take_log(), vec!["a", "b", "rest", "hook"]);
+    assert_eq!(HOOK_RAN.with(Cell::get), 1);
+}
+
+#[derive(OwnedDroppable)]
+struct PanicsInOrderedAndRest {
+    #[owned_drop(order = 0)]
+    a: Panicking,
+    rest: PanickingPlain,
+}
+
+#[test]
+fn panic_in_rest_field_does_not_discard_an_earlier_caught_panic() {
+    install_counting_hook();
+
+    let value = PanicsInOrderedAndRest {
+        a: Panicking("a"),
+        rest: PanickingPlain("rest"),
+    };
+
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        drop(DropOwned::new(value));
+    }));
+
+    assert!(result.is_ok(), "the hook should have absorbed the panic");
+    assert_eq!(take_log(), vec!["a", "rest", "hook"]);
+    assert_eq!(HOOK_RAN.with(Cell::get), 1);
+}